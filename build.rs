@@ -4,46 +4,44 @@ use std::io::Read;
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::str::FromStr;
+use std::thread;
 
 include!("src/versions.rs");
 
 #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-fn download_filename() -> String {
-    format!("electrum-{}-x86_64.AppImage", &VERSION)
+fn download_filename(version: &str) -> String {
+    format!("electrum-{}-x86_64.AppImage", version)
 }
 // other platforms are currently unsupported
 
-fn get_expected_sha256() -> Result<sha256::Hash, ()> {
-    let sha256sum_filename = format!("sha256/electrum-{}-SHA256SUM", &VERSION);
+fn get_expected_sha256(version: &str) -> Result<sha256::Hash, ()> {
+    let sha256sum_filename = format!("sha256/electrum-{}-SHA256SUM", version);
     let contents = fs::read_to_string(sha256sum_filename).expect("SHA256SUM file to exists");
     let hash = sha256::Hash::from_str(&contents).expect("SHA256SUM file to be valid");
     Ok(hash)
 }
 
-fn main() {
-    if !HAS_FEATURE || std::env::var_os("ELECTRUMD_SKIP_DOWNLOAD").is_some() {
-        return;
-    }
-    let download_filename = download_filename();
-    let expected_hash = get_expected_sha256().unwrap();
-    let out_dir = std::env::var_os("OUT_DIR").unwrap();
-    let download_dir = Path::new(&out_dir)
+// The maximum number of executables downloaded at once
+const MAX_PARALLEL_DOWNLOADS: usize = 4;
+
+fn download(out_dir: &Path, version: &str) -> Result<(), String> {
+    let download_filename = download_filename(version);
+    let expected_hash = get_expected_sha256(version).unwrap();
+    let download_dir = out_dir
         .join("electrum")
-        .join(format!("electrum-{}", VERSION));
-    if !download_dir.exists() {
-        fs::create_dir_all(&download_dir).unwrap();
-    }
+        .join(format!("electrum-{}", version));
+    fs::create_dir_all(&download_dir).map_err(|e| e.to_string())?;
     let filepath = download_dir.join("electrum.AppImage");
 
     if !filepath.exists() {
         println!(
             "filename:{} version:{} hash:{}",
-            download_filename, VERSION, expected_hash
+            download_filename, version, expected_hash
         );
 
         let url = format!(
             "https://download.electrum.org/{}/{}",
-            VERSION, download_filename
+            version, download_filename
         );
         let mut downloaded_bytes = Vec::new();
 
@@ -51,15 +49,73 @@ fn main() {
             .call()
             .into_reader()
             .read_to_end(&mut downloaded_bytes)
-            .unwrap();
+            .map_err(|e| format!("downloading {}: {}", url, e))?;
 
         let downloaded_hash = sha256::Hash::hash(&downloaded_bytes);
-        assert_eq!(expected_hash, downloaded_hash);
-        fs::write(&filepath, downloaded_bytes).unwrap();
+        if expected_hash != downloaded_hash {
+            return Err(format!(
+                "{} has SHA256 {} instead of the expected {}",
+                url, downloaded_hash, expected_hash
+            ));
+        }
+        fs::write(&filepath, downloaded_bytes).map_err(|e| e.to_string())?;
 
         // chmod +x
-        let mut perms = fs::metadata(&filepath).unwrap().permissions();
+        let mut perms = fs::metadata(&filepath)
+            .map_err(|e| e.to_string())?
+            .permissions();
         perms.set_mode(0o744);
-        fs::set_permissions(&filepath, perms).unwrap();
+        fs::set_permissions(&filepath, perms).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Download the given versions in parallel, failing with the errors of all the failed downloads
+fn download_all(out_dir: &Path, versions: &[&'static str]) {
+    let mut errors = vec![];
+    for chunk in versions.chunks(MAX_PARALLEL_DOWNLOADS) {
+        let handles: Vec<_> = chunk
+            .iter()
+            .map(|&version| {
+                let out_dir = out_dir.to_path_buf();
+                (version, thread::spawn(move || download(&out_dir, version)))
+            })
+            .collect();
+        for (version, handle) in handles {
+            match handle.join() {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => errors.push(format!("electrum {}: {}", version, e)),
+                Err(_) => errors.push(format!("electrum {}: download panicked", version)),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        panic!("failed downloading electrum:\n{}", errors.join("\n"));
+    }
+}
+
+fn main() {
+    // `ELECTRUMD_SKIP_DOWNLOAD` is also captured at compile time by the library, rerun when it
+    // changes so that a later build without it downloads the executable
+    println!("cargo:rerun-if-env-changed=ELECTRUMD_SKIP_DOWNLOAD");
+    println!("cargo:rerun-if-env-changed=ELECTRUMD_DOWNLOAD_ALL");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/versions.rs");
+    println!("cargo:rerun-if-changed=sha256");
+    if HAS_FEATURE {
+        // Allows the library to verify the downloaded executable again at runtime
+        let expected_hash = get_expected_sha256(VERSION).unwrap();
+        println!("cargo:rustc-env=ELECTRUMD_EXE_SHA256={}", expected_hash);
+    }
+    if std::env::var_os("ELECTRUMD_SKIP_DOWNLOAD").is_some() {
+        return;
+    }
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    // `ELECTRUMD_DOWNLOAD_ALL` downloads all the supported versions, to select one at runtime
+    // with `downloaded_exe_path_for`
+    if std::env::var_os("ELECTRUMD_DOWNLOAD_ALL").is_some() {
+        download_all(Path::new(&out_dir), SUPPORTED_VERSIONS);
+    } else if HAS_FEATURE {
+        download_all(Path::new(&out_dir), &[VERSION]);
     }
 }
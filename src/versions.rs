@@ -1,3 +1,8 @@
+#[cfg(all(feature = "4_1_5", feature = "4_5_4"))]
+compile_error!("only one electrum version feature can be enabled (`latest` enables `4_5_4`)");
+
+pub const SUPPORTED_VERSIONS: &[&str] = &["4.1.5", "4.5.4"];
+
 pub const HAS_FEATURE: bool = cfg!(any(feature = "4_1_5", feature = "4_5_4"));
 
 #[cfg(not(any(feature = "4_1_5", feature = "4_5_4")))]
@@ -0,0 +1,16 @@
+// Kept in its own test binary, as instances launched by concurrently running tests would
+// otherwise show up in the process-wide counter
+
+use electrumd::{active_instances, exe_path, ElectrumD};
+
+#[test]
+fn test_active_instances() {
+    let exe = exe_path().unwrap();
+    let instances = (0..3)
+        .map(|_| ElectrumD::new(&exe).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(active_instances(), 3);
+
+    drop(instances);
+    assert_eq!(active_instances(), 0);
+}